@@ -1,5 +1,10 @@
 use std::process::exit;
 
+/// Logs `message` and terminates the process.
+///
+/// This is a convenience for the CLI front-end only; library-facing parsing
+/// code must return a [`crate::error::FirmwareError`] instead so embedders
+/// can handle failures without losing their process.
 pub fn exit_with_cause(message: &str) {
     error!("{}", message);
     exit(1);
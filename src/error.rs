@@ -0,0 +1,26 @@
+use thiserror::Error;
+
+/// Errors produced while parsing firmware metadata or server responses.
+///
+/// This is the crate's library-facing error type: parsing code returns it
+/// instead of aborting the process, so embedders can decide how to react.
+#[derive(Debug, Error)]
+pub enum FirmwareError {
+    #[error("response is empty")]
+    EmptyResponse,
+
+    #[error("invalid json: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+
+    #[error("response is missing the `status` field")]
+    MissingStatusField,
+
+    #[error("http error: {0}")]
+    HttpError(#[from] reqwest::Error),
+
+    #[error("response data does not describe valid firmware metadata")]
+    InvalidFirmwareMetadata,
+
+    #[error("downloaded firmware does not match the advertised checksum")]
+    ChecksumMismatch,
+}
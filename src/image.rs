@@ -0,0 +1,303 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+use crate::{Firmware, FirmwareEdition, FirmwareVersion};
+
+/// 4-byte magic identifying an on-disk firmware container.
+const MAGIC: [u8; 4] = *b"OFWB";
+
+/// Current on-disk format version written by [`FirmwareImage::write_to`].
+const FORMAT_VERSION: u16 = 1;
+
+#[derive(Debug, Error)]
+pub enum FirmwareImageError {
+    #[error("buffer is too short to contain a firmware image header")]
+    TooShort,
+
+    #[error("missing or invalid magic bytes")]
+    InvalidMagic,
+
+    #[error("unrecognized firmware edition byte")]
+    InvalidEdition,
+
+    #[error("CRC-32 checksum does not match the image contents")]
+    InvalidCrc,
+
+    #[error("serial number is not valid UTF-8")]
+    InvalidSerialNumber,
+
+    #[error("stored version triplet could not be parsed")]
+    InvalidVersion,
+
+    #[error("version component does not fit in the on-disk u8 field")]
+    VersionOutOfRange,
+
+    #[error("serial number is too long to fit in the on-disk u16 length prefix")]
+    SerialTooLong,
+
+    #[error("payload is too long to fit in the on-disk u32 length prefix")]
+    PayloadTooLong,
+}
+
+/// A self-describing binary firmware container: magic bytes, a fixed header,
+/// the raw payload, and a trailing CRC-32 over everything that precedes it.
+///
+/// ```text
+/// magic(4) | format_version(2) | edition(1) | major(1) | minor(1) | patch(1)
+/// | compile_time(8) | serial_number_len(2) | serial_number | payload_len(4)
+/// | payload | crc32(4)
+/// ```
+///
+/// The header's version triplet only has room for three `u8` fields, so the
+/// round-trip through [`FirmwareImage::write_to`]/[`FirmwareImage::read_from`]
+/// is lossy: any [`FirmwareVersion`] granularity other than `SemVer` (and any
+/// pre-release/build metadata) collapses to a plain `major.minor.patch`, and
+/// a component above `u8::MAX` is rejected rather than silently truncated.
+#[derive(Debug, Clone)]
+pub struct FirmwareImage {
+    format_version: u16,
+    edition: FirmwareEdition,
+    version: FirmwareVersion,
+    compile_time: i64,
+    serial_number: String,
+    payload: Vec<u8>,
+}
+
+impl FirmwareImage {
+    pub fn new(
+        edition: FirmwareEdition,
+        version: FirmwareVersion,
+        compile_time: i64,
+        serial_number: String,
+        payload: Vec<u8>,
+    ) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            edition,
+            version,
+            compile_time,
+            serial_number,
+            payload,
+        }
+    }
+
+    pub fn from_firmware(firmware: &Firmware, payload: Vec<u8>) -> Self {
+        Self::new(
+            firmware.edition().clone(),
+            firmware.version().clone(),
+            firmware.compile_time().timestamp(),
+            firmware.serial_number().clone(),
+            payload,
+        )
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Serializes the header, payload, and trailing CRC-32 into `out`.
+    ///
+    /// Fails with [`FirmwareImageError::VersionOutOfRange`] rather than
+    /// truncating if a version component does not fit in the on-disk `u8`
+    /// field; see the lossiness note on [`FirmwareImage`] itself.
+    pub fn write_to(&self, out: &mut Vec<u8>) -> Result<(), FirmwareImageError> {
+        let major = u8::try_from(self.version.major())
+            .map_err(|_| FirmwareImageError::VersionOutOfRange)?;
+        let minor = u8::try_from(self.version.minor())
+            .map_err(|_| FirmwareImageError::VersionOutOfRange)?;
+        let patch = u8::try_from(self.version.patch())
+            .map_err(|_| FirmwareImageError::VersionOutOfRange)?;
+        let serial_number_len = u16::try_from(self.serial_number.len())
+            .map_err(|_| FirmwareImageError::SerialTooLong)?;
+        let payload_len = u32::try_from(self.payload.len())
+            .map_err(|_| FirmwareImageError::PayloadTooLong)?;
+
+        let start = out.len();
+        out.extend_from_slice(&MAGIC);
+        out.extend_from_slice(&self.format_version.to_le_bytes());
+        out.push(edition_to_byte(&self.edition));
+        out.push(major);
+        out.push(minor);
+        out.push(patch);
+        out.extend_from_slice(&self.compile_time.to_le_bytes());
+        out.extend_from_slice(&serial_number_len.to_le_bytes());
+        out.extend_from_slice(self.serial_number.as_bytes());
+        out.extend_from_slice(&payload_len.to_le_bytes());
+        out.extend_from_slice(&self.payload);
+        let crc = crc32(&out[start..]);
+        out.extend_from_slice(&crc.to_le_bytes());
+        Ok(())
+    }
+
+    /// Deserializes a container written by [`FirmwareImage::write_to`].
+    ///
+    /// The version is always reconstructed as a `major.minor.patch` triplet
+    /// (the on-disk header has no room for anything else); see the
+    /// lossiness note on [`FirmwareImage`] itself.
+    pub fn read_from(bytes: &[u8]) -> Result<Self, FirmwareImageError> {
+        if bytes.len() < 4 {
+            return Err(FirmwareImageError::TooShort);
+        }
+
+        let (body, trailing_crc) = bytes.split_at(bytes.len() - 4);
+        if trailing_crc.len() != 4 {
+            return Err(FirmwareImageError::TooShort);
+        }
+        let expected_crc = u32::from_le_bytes(trailing_crc.try_into().unwrap());
+        if crc32(body) != expected_crc {
+            return Err(FirmwareImageError::InvalidCrc);
+        }
+
+        let mut cursor = Cursor::new(body);
+        let magic = cursor.take(4)?;
+        if magic != MAGIC {
+            return Err(FirmwareImageError::InvalidMagic);
+        }
+
+        let format_version = u16::from_le_bytes(cursor.take(2)?.try_into().unwrap());
+        let edition_byte = cursor.take(1)?[0];
+        let edition = edition_from_byte(edition_byte)?;
+        let major = cursor.take(1)?[0];
+        let minor = cursor.take(1)?[0];
+        let patch = cursor.take(1)?[0];
+        let compile_time = i64::from_le_bytes(cursor.take(8)?.try_into().unwrap());
+
+        let serial_number_len = u16::from_le_bytes(cursor.take(2)?.try_into().unwrap()) as usize;
+        let serial_number = String::from_utf8(cursor.take(serial_number_len)?.to_vec())
+            .map_err(|_| FirmwareImageError::InvalidSerialNumber)?;
+
+        let payload_len = u32::from_le_bytes(cursor.take(4)?.try_into().unwrap()) as usize;
+        let payload = cursor.take(payload_len)?.to_vec();
+
+        let version = FirmwareVersion::from_str(&format!("{}.{}.{}", major, minor, patch))
+            .map_err(|_| FirmwareImageError::InvalidVersion)?;
+
+        Ok(Self {
+            format_version,
+            edition,
+            version,
+            compile_time,
+            serial_number,
+            payload,
+        })
+    }
+}
+
+/// A tiny cursor over a byte slice used to read the fixed-width header
+/// fields in order without re-deriving offsets by hand.
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], FirmwareImageError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .ok_or(FirmwareImageError::TooShort)?;
+        let slice = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(FirmwareImageError::TooShort)?;
+        self.position = end;
+        Ok(slice)
+    }
+}
+
+fn edition_to_byte(edition: &FirmwareEdition) -> u8 {
+    match edition {
+        FirmwareEdition::Standard => 0,
+        FirmwareEdition::Plus => 1,
+        FirmwareEdition::Premium => 2,
+    }
+}
+
+fn edition_from_byte(byte: u8) -> Result<FirmwareEdition, FirmwareImageError> {
+    match byte {
+        0 => Ok(FirmwareEdition::Standard),
+        1 => Ok(FirmwareEdition::Plus),
+        2 => Ok(FirmwareEdition::Premium),
+        _ => Err(FirmwareImageError::InvalidEdition),
+    }
+}
+
+/// Standard CRC-32 (IEEE 802.3) over `data`, matching the polynomial used by
+/// `zlib`/`crc32` elsewhere in the toolchain.
+fn crc32(data: &[u8]) -> u32 {
+    const POLY: u32 = 0xEDB88320;
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (POLY & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::{FirmwareImage, FirmwareImageError};
+    use crate::{FirmwareEdition, FirmwareVersion};
+
+    fn sample_image() -> FirmwareImage {
+        FirmwareImage::new(
+            FirmwareEdition::Plus,
+            FirmwareVersion::from_str("5.2.1").unwrap(),
+            1_700_000_000,
+            "SN-001".to_owned(),
+            vec![1, 2, 3, 4, 5],
+        )
+    }
+
+    #[test]
+    fn write_then_read_round_trips() {
+        let image = sample_image();
+        let mut bytes = Vec::new();
+        image.write_to(&mut bytes).unwrap();
+
+        let read_back = FirmwareImage::read_from(&bytes).unwrap();
+        assert_eq!(read_back.payload(), image.payload());
+    }
+
+    #[test]
+    fn corrupted_payload_is_rejected_by_crc() {
+        let image = sample_image();
+        let mut bytes = Vec::new();
+        image.write_to(&mut bytes).unwrap();
+
+        // The payload's last byte sits just before the 4-byte trailing CRC.
+        let last_payload_byte = bytes.len() - 4 - 1;
+        bytes[last_payload_byte] ^= 0xFF;
+
+        assert!(matches!(
+            FirmwareImage::read_from(&bytes),
+            Err(FirmwareImageError::InvalidCrc)
+        ));
+    }
+
+    #[test]
+    fn version_component_above_u8_max_is_rejected() {
+        let image = FirmwareImage::new(
+            FirmwareEdition::Standard,
+            FirmwareVersion::from_str("1.2.301").unwrap(),
+            1_700_000_000,
+            "SN-002".to_owned(),
+            vec![],
+        );
+        let mut bytes = Vec::new();
+        assert!(matches!(
+            image.write_to(&mut bytes),
+            Err(FirmwareImageError::VersionOutOfRange)
+        ));
+    }
+}
@@ -1,4 +1,5 @@
-use crate::commandline::exit_with_cause;
+use crate::error::FirmwareError;
+use crate::Firmware;
 
 #[derive(Debug)]
 pub struct ResultResponse {
@@ -10,26 +11,22 @@ pub struct ResultResponse {
 
 #[allow(unused)]
 impl ResultResponse {
-    pub fn new(response: String) -> ResultResponse {
+    pub fn new(response: String) -> Result<ResultResponse, FirmwareError> {
         let mut result = ResultResponse {
             response,
             ok_or_fail: false,
             message: String::new(),
             data: String::new(),
         };
-        result.parse_response();
-        result
+        result.parse_response()?;
+        Ok(result)
     }
 
-    pub fn from_result(result: Result<reqwest::blocking::Response, reqwest::Error>) -> Self {
-        if result.as_ref().is_err() {
-            exit_with_cause("cannot unpack response!");
-        }
-        let text = result.unwrap().text();
-        if text.is_err() {
-            exit_with_cause("cannot unpack response!");
-        }
-        return Self::new(text.unwrap().clone());
+    pub fn from_result(
+        result: Result<reqwest::blocking::Response, reqwest::Error>,
+    ) -> Result<Self, FirmwareError> {
+        let text = result?.text()?;
+        Self::new(text)
     }
 
     pub fn is_ok(&self) -> bool {
@@ -58,21 +55,54 @@ impl ResultResponse {
         &self.data
     }
 
-    fn parse_response(&mut self) {
-        if self.response.is_empty() {
-            exit_with_cause("response is empty!");
+    /// Parses the `data` payload into [`Firmware`] and, given the raw
+    /// downloaded `payload`, refuses to return it unless the payload's
+    /// SHA-256 digest matches the advertised checksum.
+    pub fn verified_firmware(&self, payload: &[u8]) -> Result<Firmware, FirmwareError> {
+        let firmware = self.parse_firmware()?;
+        if !firmware.verify(payload) {
+            return Err(FirmwareError::ChecksumMismatch);
         }
+        Ok(firmware)
+    }
 
-        let json_result = serde_json::from_str(&self.response);
-        if json_result.is_err() {
-            exit_with_cause("invalid json error");
+    fn parse_firmware(&self) -> Result<Firmware, FirmwareError> {
+        let json: serde_json::Value = serde_json::from_str(&self.data)?;
+        let field = |name: &str| -> Result<String, FirmwareError> {
+            json[name]
+                .as_str()
+                .map(str::to_owned)
+                .ok_or(FirmwareError::InvalidFirmwareMetadata)
+        };
+        let size = json["size"]
+            .as_u64()
+            .ok_or(FirmwareError::InvalidFirmwareMetadata)?;
+        Firmware::from(
+            field("serial_number")?,
+            size,
+            field("compile_time")?,
+            field("edition")?,
+            field("version")?,
+            field("checksum")?,
+            field("arch")?,
+        )
+        .ok_or(FirmwareError::InvalidFirmwareMetadata)
+    }
+
+    fn parse_response(&mut self) -> Result<(), FirmwareError> {
+        if self.response.is_empty() {
+            return Err(FirmwareError::EmptyResponse);
         }
-        let json: serde_json::Value = json_result.unwrap();
-        self.ok_or_fail = json["status"].as_bool().unwrap();
+
+        let json: serde_json::Value = serde_json::from_str(&self.response)?;
+        self.ok_or_fail = json["status"]
+            .as_bool()
+            .ok_or(FirmwareError::MissingStatusField)?;
         self.message = json["message"].as_str().unwrap_or("").to_owned();
         let data_value = &json["data"];
         if let Some(data_obj) = data_value.as_object() {
             self.data = serde_json::to_string(data_obj).unwrap_or_default();
         }
+        Ok(())
     }
 }
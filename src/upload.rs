@@ -0,0 +1,195 @@
+use std::io::{self, Read, Write};
+
+use thiserror::Error;
+
+use crate::image::{FirmwareImage, FirmwareImageError};
+
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+const ACK: u8 = 0x06;
+const NAK: u8 = 0x15;
+
+const DEFAULT_MAX_RETRIES: u8 = 5;
+const CHUNK_SIZE: usize = 256;
+
+#[derive(Debug, Error)]
+pub enum UploadError {
+    #[error("i/o error talking to the device: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("device replied with unexpected byte {0:#04x} (expected ACK or NAK)")]
+    UnexpectedReply(u8),
+
+    #[error("gave up after exhausting all retries for a frame")]
+    MaxRetriesExceeded,
+
+    #[error("failed to serialize the firmware image: {0}")]
+    Image(#[from] FirmwareImageError),
+}
+
+/// Pushes a firmware image to a device over a serial/UART link using a
+/// framed STX/length/seq/data/ETX/checksum protocol, retransmitting a frame
+/// on NAK or read timeout up to `max_retries` times before giving up.
+pub struct FirmwareUploader<S: Read + Write> {
+    stream: S,
+    max_retries: u8,
+}
+
+impl<S: Read + Write> FirmwareUploader<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            max_retries: DEFAULT_MAX_RETRIES,
+        }
+    }
+
+    pub fn with_max_retries(stream: S, max_retries: u8) -> Self {
+        Self {
+            stream,
+            max_retries,
+        }
+    }
+
+    /// Serializes `image` and uploads it chunk by chunk, calling
+    /// `on_progress(chunks_sent, total_chunks)` after each acknowledged frame.
+    pub fn upload_image(
+        &mut self,
+        image: &FirmwareImage,
+        on_progress: impl FnMut(usize, usize),
+    ) -> Result<(), UploadError> {
+        let mut bytes = Vec::new();
+        image.write_to(&mut bytes)?;
+        self.upload(&bytes, on_progress)
+    }
+
+    pub fn upload(
+        &mut self,
+        payload: &[u8],
+        mut on_progress: impl FnMut(usize, usize),
+    ) -> Result<(), UploadError> {
+        let chunks: Vec<&[u8]> = payload.chunks(CHUNK_SIZE).collect();
+        let total = chunks.len();
+        for (index, chunk) in chunks.into_iter().enumerate() {
+            let sequence = (index % 256) as u8;
+            self.send_chunk(sequence, chunk)?;
+            on_progress(index + 1, total);
+        }
+        Ok(())
+    }
+
+    fn send_chunk(&mut self, sequence: u8, chunk: &[u8]) -> Result<(), UploadError> {
+        let frame = encode_frame(sequence, chunk);
+        // One initial send plus up to `max_retries` retransmissions.
+        for _ in 0..=self.max_retries {
+            self.stream.write_all(&frame)?;
+            self.stream.flush()?;
+
+            let mut reply = [0u8; 1];
+            match self.stream.read_exact(&mut reply) {
+                Ok(()) => match reply[0] {
+                    ACK => return Ok(()),
+                    NAK => continue,
+                    other => return Err(UploadError::UnexpectedReply(other)),
+                },
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    continue
+                }
+                Err(error) => return Err(UploadError::Io(error)),
+            }
+        }
+        Err(UploadError::MaxRetriesExceeded)
+    }
+}
+
+fn encode_frame(sequence: u8, chunk: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(chunk.len() + 6);
+    frame.push(STX);
+    frame.extend_from_slice(&(chunk.len() as u16).to_le_bytes());
+    frame.push(sequence);
+    frame.extend_from_slice(chunk);
+    frame.push(ETX);
+    frame.push(chunk.iter().fold(0u8, |checksum, byte| checksum ^ byte));
+    frame
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io;
+
+    use super::*;
+
+    #[test]
+    fn encode_frame_lays_out_stx_length_seq_etx_and_xor_checksum() {
+        let chunk = [0x10, 0x20, 0x30];
+        let frame = encode_frame(7, &chunk);
+
+        assert_eq!(frame[0], STX);
+        assert_eq!(u16::from_le_bytes([frame[1], frame[2]]), chunk.len() as u16);
+        assert_eq!(frame[3], 7);
+        assert_eq!(&frame[4..7], &chunk);
+        assert_eq!(frame[7], ETX);
+        assert_eq!(frame[8], 0x10 ^ 0x20 ^ 0x30);
+    }
+
+    /// An in-memory `Read + Write` stream that records written frames and
+    /// plays back a scripted sequence of ACK/NAK replies, standing in for a
+    /// real serial device in the retry tests below.
+    struct MockStream {
+        replies: VecDeque<u8>,
+        written: Vec<u8>,
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.replies.pop_front() {
+                Some(reply) => {
+                    buf[0] = reply;
+                    Ok(1)
+                }
+                None => Err(io::Error::from(io::ErrorKind::TimedOut)),
+            }
+        }
+    }
+
+    #[test]
+    fn retransmits_on_nak_then_succeeds_on_ack() {
+        let stream = MockStream {
+            replies: VecDeque::from([NAK, ACK]),
+            written: Vec::new(),
+        };
+        let mut uploader = FirmwareUploader::new(stream);
+
+        uploader.upload(&[1, 2, 3], |_, _| {}).unwrap();
+    }
+
+    #[test]
+    fn gives_up_after_max_retries_of_nak() {
+        let stream = MockStream {
+            replies: VecDeque::from(vec![NAK; 10]),
+            written: Vec::new(),
+        };
+        let mut uploader = FirmwareUploader::with_max_retries(stream, 3);
+
+        assert!(matches!(
+            uploader.upload(&[1, 2, 3], |_, _| {}),
+            Err(UploadError::MaxRetriesExceeded)
+        ));
+    }
+}